@@ -0,0 +1,163 @@
+//! Minimal DER helpers for picking the channel-binding hash algorithm.
+//!
+//! RFC 5929 defines `tls-server-end-point` as the hash of the peer
+//! certificate using the hash algorithm from the certificate's own
+//! signature algorithm, falling back to SHA-256 when that algorithm is
+//! MD5 or SHA-1 (or anything else we don't recognize). Pulling in a full
+//! X.509 parser just to read one OID would be overkill, so this module
+//! walks the handful of SEQUENCE/OID TLVs we actually need.
+
+use ring::digest;
+
+/// Digests `cert_der` with the hash algorithm implied by its signature
+/// algorithm, per RFC 5929's rules for the `tls-server-end-point` binding.
+pub(crate) fn end_point_digest(cert_der: &[u8]) -> digest::Digest {
+    let algorithm = signature_algorithm_oid(cert_der)
+        .and_then(digest_algorithm_for_oid)
+        .unwrap_or(&digest::SHA256);
+    digest::digest(algorithm, cert_der)
+}
+
+/// Reads a single BER/DER TLV from the front of `data`, returning its tag,
+/// content bytes, and the remainder of `data` after the TLV.
+fn read_tlv(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let &tag = data.first()?;
+    let len_byte = *data.get(1)?;
+    let (len, header_len) = if len_byte & 0x80 == 0 {
+        (len_byte as usize, 2usize)
+    } else {
+        let num_len_bytes = (len_byte & 0x7f) as usize;
+        if num_len_bytes == 0 || num_len_bytes > 4 {
+            return None;
+        }
+        let len_bytes = data.get(2..2 + num_len_bytes)?;
+        let mut len = 0usize;
+        for &b in len_bytes {
+            len = (len << 8) | b as usize;
+        }
+        (len, 2 + num_len_bytes)
+    };
+    let content = data.get(header_len..header_len + len)?;
+    let rest = &data[header_len + len..];
+    Some((tag, content, rest))
+}
+
+/// Extracts the `signatureAlgorithm` OID (the second field of the outer
+/// `Certificate` SEQUENCE) from a DER-encoded X.509 certificate.
+fn signature_algorithm_oid(cert_der: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+    const OBJECT_IDENTIFIER: u8 = 0x06;
+
+    let (tag, certificate, _) = read_tlv(cert_der)?;
+    if tag != SEQUENCE {
+        return None;
+    }
+    let (tag, _tbs_certificate, rest) = read_tlv(certificate)?;
+    if tag != SEQUENCE {
+        return None;
+    }
+    let (tag, algorithm_identifier, _) = read_tlv(rest)?;
+    if tag != SEQUENCE {
+        return None;
+    }
+    let (tag, oid, _) = read_tlv(algorithm_identifier)?;
+    if tag != OBJECT_IDENTIFIER {
+        return None;
+    }
+    Some(oid)
+}
+
+/// Maps a signature algorithm OID to the `ring` digest algorithm it signs
+/// over, substituting SHA-256 for MD5/SHA-1 as RFC 5929 requires. Returns
+/// `None` for unknown OIDs; [`end_point_digest`] then uses SHA-256.
+fn digest_algorithm_for_oid(oid: &[u8]) -> Option<&'static digest::Algorithm> {
+    // DER-encoded OID bytes (the `06 <len>` header stripped off) for the
+    // signature algorithms we're likely to see in the wild.
+    const MD5_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x04];
+    const SHA1_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x05];
+    const SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+    const SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+    const SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+    const ECDSA_WITH_SHA1: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x01];
+    const ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+    const ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+    const ECDSA_WITH_SHA512: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x04];
+
+    Some(match oid {
+        MD5_WITH_RSA | SHA1_WITH_RSA | ECDSA_WITH_SHA1 => &digest::SHA256,
+        SHA256_WITH_RSA | ECDSA_WITH_SHA256 => &digest::SHA256,
+        SHA384_WITH_RSA | ECDSA_WITH_SHA384 => &digest::SHA384,
+        SHA512_WITH_RSA | ECDSA_WITH_SHA512 => &digest::SHA512,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal `Certificate` DER structure with the given
+    /// `signatureAlgorithm` OID, enough for `signature_algorithm_oid` to
+    /// find it.
+    fn certificate_with_signature_oid(oid: &[u8]) -> Vec<u8> {
+        let mut algorithm_identifier = vec![0x06, oid.len() as u8];
+        algorithm_identifier.extend_from_slice(oid);
+        let mut algorithm_identifier_seq = vec![0x30, algorithm_identifier.len() as u8];
+        algorithm_identifier_seq.extend_from_slice(&algorithm_identifier);
+
+        let tbs_certificate = vec![0x30, 0x00];
+
+        let mut certificate = vec![0x30, 0x00]; // Certificate SEQUENCE, length patched below
+        certificate.extend_from_slice(&tbs_certificate);
+        certificate.extend_from_slice(&algorithm_identifier_seq);
+        let content_len = certificate.len() - 2;
+        certificate[1] = content_len as u8;
+        certificate
+    }
+
+    #[test]
+    fn selects_sha384_for_sha384_with_rsa() {
+        let cert = certificate_with_signature_oid(&[
+            0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c,
+        ]);
+        let oid = signature_algorithm_oid(&cert).expect("oid");
+        assert_eq!(digest_algorithm_for_oid(oid), Some(&digest::SHA384));
+    }
+
+    #[test]
+    fn selects_sha512_for_ecdsa_with_sha512() {
+        let cert =
+            certificate_with_signature_oid(&[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x04]);
+        let oid = signature_algorithm_oid(&cert).expect("oid");
+        assert_eq!(digest_algorithm_for_oid(oid), Some(&digest::SHA512));
+    }
+
+    #[test]
+    fn falls_back_to_sha256_for_sha1_with_rsa() {
+        let cert = certificate_with_signature_oid(&[
+            0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x05,
+        ]);
+        let oid = signature_algorithm_oid(&cert).expect("oid");
+        assert_eq!(digest_algorithm_for_oid(oid), Some(&digest::SHA256));
+    }
+
+    #[test]
+    fn falls_back_to_sha256_for_unknown_oid() {
+        let cert = certificate_with_signature_oid(&[0x2a, 0x03]);
+        let oid = signature_algorithm_oid(&cert).expect("oid");
+        assert_eq!(digest_algorithm_for_oid(oid), None);
+        // `end_point_digest` substitutes SHA-256 for an unrecognized OID.
+        let expected = digest::digest(&digest::SHA256, &cert);
+        assert_eq!(end_point_digest(&cert).as_ref(), expected.as_ref());
+    }
+
+    #[test]
+    fn end_point_digest_matches_sha384_of_whole_cert() {
+        let cert = certificate_with_signature_oid(&[
+            0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c,
+        ]);
+        let expected = digest::digest(&digest::SHA384, &cert);
+        let actual = end_point_digest(&cert);
+        assert_eq!(actual.as_ref(), expected.as_ref());
+    }
+}
@@ -5,26 +5,94 @@ use std::{
     pin::Pin,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
 use futures::future::{FutureExt, TryFutureExt};
-use ring::digest;
-use rustls::ClientConfig;
-use rustls::pki_types::ServerName;
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::{ClientConfig, ProtocolVersion, SupportedCipherSuite};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio_postgres::tls::{ChannelBinding, MakeTlsConnect, TlsConnect};
 use tokio_rustls::{client::TlsStream, TlsConnector};
 
+mod cert_hash;
+#[cfg(feature = "dangerous-configuration")]
+mod dangerous;
+
 #[derive(Clone)]
 pub struct MakeRustlsConnect {
     config: Arc<ClientConfig>,
+    handshake_timeout: Option<Duration>,
 }
 
 impl MakeRustlsConnect {
     pub fn new(config: ClientConfig) -> Self {
         Self {
             config: Arc::new(config),
+            handshake_timeout: None,
+        }
+    }
+
+    /// Builds a connector trusting the platform's native certificate store,
+    /// loaded via `rustls-native-certs`.
+    ///
+    /// This covers the common "just connect securely" case without callers
+    /// having to assemble a [`ClientConfig`] and [`rustls::RootCertStore`]
+    /// by hand. For advanced setups (custom roots, client auth, CRLs), use
+    /// [`MakeRustlsConnect::new`] instead.
+    #[cfg(feature = "native-certs")]
+    pub fn with_native_roots() -> io::Result<Self> {
+        let mut roots = rustls::RootCertStore::empty();
+        for cert in rustls_native_certs::load_native_certs()? {
+            roots
+                .add(cert)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
         }
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Ok(Self::new(config))
+    }
+
+    /// Builds a connector trusting the Mozilla root program bundle shipped
+    /// in `webpki-roots`.
+    ///
+    /// Unlike [`MakeRustlsConnect::with_native_roots`] this doesn't touch
+    /// the OS trust store, so it works the same way on every platform and
+    /// needs no I/O at startup.
+    #[cfg(feature = "webpki-roots")]
+    pub fn with_webpki_roots() -> Self {
+        let roots = rustls::RootCertStore {
+            roots: webpki_roots::TLS_SERVER_ROOTS.into(),
+        };
+        let config = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Self::new(config)
+    }
+
+    /// Disables server certificate verification entirely.
+    ///
+    /// This is for local/dev/testing setups only (e.g. connecting to a
+    /// self-signed Postgres instance in CI) — it removes TLS's protection
+    /// against MITM attacks, so never enable it against anything reachable
+    /// by an untrusted network.
+    #[cfg(feature = "dangerous-configuration")]
+    pub fn dangerous_accept_any_certificate(mut self) -> Self {
+        let mut config = (*self.config).clone();
+        config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(dangerous::NoCertificateVerification));
+        self.config = Arc::new(config);
+        self
+    }
+
+    /// Fails the handshake with [`io::ErrorKind::TimedOut`] if it hasn't
+    /// completed within `timeout`, instead of leaving a pooled connection
+    /// stuck waiting on a stalled peer indefinitely.
+    pub fn with_handshake_timeout(mut self, timeout: Duration) -> Self {
+        self.handshake_timeout = Some(timeout);
+        self
     }
 }
 
@@ -37,22 +105,41 @@ where
     type Error = io::Error;
 
     fn make_tls_connect(&mut self, hostname: &str) -> io::Result<RustlsConnect> {
-        ServerName::try_from(hostname)
-            .map(|dns_name| {
-                RustlsConnect(Some(RustlsConnectData {
-                    hostname: dns_name.to_owned(),
-                    connector: Arc::clone(&self.config).into(),
-                }))
-            })
-            .or(Ok(RustlsConnect(None)))
+        let hostname = parse_server_name(hostname)?;
+        Ok(RustlsConnect(RustlsConnectData {
+            hostname,
+            connector: Arc::clone(&self.config).into(),
+            handshake_timeout: self.handshake_timeout,
+        }))
+    }
+}
+
+/// Parses a `host` connection parameter into a [`ServerName`], accepting
+/// both DNS names and IP address literals (so `host=127.0.0.1` and
+/// `host=::1` validate against SAN-IP entries instead of being rejected).
+///
+/// Returns a descriptive [`io::Error`] on failure instead of deferring to
+/// an opaque `InvalidInput` error at connect time.
+fn parse_server_name(hostname: &str) -> io::Result<ServerName<'static>> {
+    if let Ok(ip) = hostname.parse::<std::net::IpAddr>() {
+        return Ok(ServerName::IpAddress(ip.into()));
     }
+    ServerName::try_from(hostname)
+        .map(|name| name.to_owned())
+        .map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid TLS server name {hostname:?}: {e}"),
+            )
+        })
 }
 
-pub struct RustlsConnect(Option<RustlsConnectData>);
+pub struct RustlsConnect(RustlsConnectData);
 
 struct RustlsConnectData {
     hostname: ServerName<'static>,
     connector: TlsConnector,
+    handshake_timeout: Option<Duration>,
 }
 
 impl<S> TlsConnect<S> for RustlsConnect
@@ -64,29 +151,58 @@ where
     type Future = Pin<Box<dyn Future<Output = io::Result<RustlsStream<S>>> + Send>>;
 
     fn connect(self, stream: S) -> Self::Future {
-        match self.0 {
-            None => Box::pin(core::future::ready(Err(io::ErrorKind::InvalidInput.into()))),
-            Some(c) => c
-                .connector
-                .connect(c.hostname, stream)
-                .map_ok(|s| RustlsStream(Box::pin(s)))
-                .boxed(),
+        let c = self.0;
+        let handshake = c
+            .connector
+            .connect(c.hostname, stream)
+            .map_ok(|s| RustlsStream(Box::pin(s)));
+        match c.handshake_timeout {
+            Some(timeout) => Box::pin(async move {
+                tokio::time::timeout(timeout, handshake)
+                    .await
+                    .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into()))
+            }),
+            None => handshake.boxed(),
         }
     }
 }
 
 pub struct RustlsStream<S>(Pin<Box<TlsStream<S>>>);
 
+impl<S> RustlsStream<S> {
+    /// The TLS protocol version negotiated with the server, e.g. `TLSv1_3`.
+    ///
+    /// Returns `None` if the handshake has not completed yet, which
+    /// shouldn't happen for a stream handed back by [`RustlsConnect`].
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.0.get_ref().1.protocol_version()
+    }
+
+    /// The cipher suite negotiated with the server.
+    pub fn negotiated_cipher_suite(&self) -> Option<SupportedCipherSuite> {
+        self.0.get_ref().1.negotiated_cipher_suite()
+    }
+
+    /// The application-layer protocol negotiated via ALPN, if any.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.0.get_ref().1.alpn_protocol()
+    }
+
+    /// The certificate chain presented by the server.
+    pub fn peer_certificates(&self) -> Option<&[CertificateDer<'static>]> {
+        self.0.get_ref().1.peer_certificates()
+    }
+}
+
 impl<S> tokio_postgres::tls::TlsStream for RustlsStream<S>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
     fn channel_binding(&self) -> ChannelBinding {
-        let (_, session) = self.0.get_ref();
-        match session.peer_certificates() {
+        match self.peer_certificates() {
             Some(certs) if !certs.is_empty() => {
-                let sha256 = digest::digest(&digest::SHA256, certs[0].as_ref());
-                ChannelBinding::tls_server_end_point(sha256.as_ref().into())
+                let digest = cert_hash::end_point_digest(certs[0].as_ref());
+                ChannelBinding::tls_server_end_point(digest.as_ref().into())
             }
             _ => ChannelBinding::none(),
         }